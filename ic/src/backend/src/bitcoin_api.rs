@@ -0,0 +1,86 @@
+use ic_cdk::api::management_canister::bitcoin::{
+    bitcoin_get_balance, bitcoin_get_current_fee_percentiles, bitcoin_get_utxos,
+    bitcoin_send_transaction, BitcoinNetwork, GetBalanceRequest,
+    GetCurrentFeePercentilesRequest, GetUtxosRequest, GetUtxosResponse, MillisatoshiPerByte,
+    Satoshi, SendTransactionRequest, UtxoFilter,
+};
+
+// Cycles cost charged by the management canister for each Bitcoin API call. See
+// https://internetcomputer.org/docs/current/developer-docs/multi-chain/bitcoin/using-btc/costs-latency
+const GET_BALANCE_COST_CYCLES: u64 = 100_000_000;
+const GET_UTXOS_COST_CYCLES: u64 = 100_000_000;
+const GET_CURRENT_FEE_PERCENTILES_COST_CYCLES: u64 = 100_000_000;
+// `send_transaction` is priced per byte on top of a base fee, so `send_transaction` below attaches
+// the base fee plus the per-byte fee for the serialized transaction's length.
+const SEND_TRANSACTION_BASE_CYCLES: u64 = 5_000_000_000;
+const SEND_TRANSACTION_PER_BYTE_CYCLES: u64 = 20_000_000;
+
+/// Returns the balance of the given Bitcoin address, counting only UTXOs with at least
+/// `min_confirmations` confirmations (management canister default if `None`).
+pub async fn get_balance(
+    network: BitcoinNetwork,
+    address: String,
+    min_confirmations: Option<u32>,
+) -> Satoshi {
+    let request = GetBalanceRequest {
+        address,
+        network,
+        min_confirmations,
+    };
+
+    let (balance,) = bitcoin_get_balance(request, GET_BALANCE_COST_CYCLES)
+        .await
+        .expect("failed to call bitcoin_get_balance");
+    balance
+}
+
+/// Returns the UTXOs of the given Bitcoin address, resuming from `page` if it was returned by a
+/// previous call, or an error message if the management canister call itself fails (rather than
+/// trapping the caller's update call).
+pub async fn get_utxos(
+    network: BitcoinNetwork,
+    address: String,
+    page: Option<Vec<u8>>,
+) -> Result<GetUtxosResponse, String> {
+    let request = GetUtxosRequest {
+        address,
+        network,
+        filter: page.map(UtxoFilter::Page),
+    };
+
+    let (response,) = bitcoin_get_utxos(request, GET_UTXOS_COST_CYCLES)
+        .await
+        .map_err(|(_, message)| format!("failed to fetch UTXOs: {message}"))?;
+    Ok(response)
+}
+
+/// Returns the 100 fee percentiles measured in millisatoshi/byte over recent transactions.
+pub async fn get_current_fee_percentiles(network: BitcoinNetwork) -> Vec<MillisatoshiPerByte> {
+    let request = GetCurrentFeePercentilesRequest { network };
+
+    let (percentiles,) = bitcoin_get_current_fee_percentiles(
+        request,
+        GET_CURRENT_FEE_PERCENTILES_COST_CYCLES,
+    )
+    .await
+    .expect("failed to call bitcoin_get_current_fee_percentiles");
+    percentiles
+}
+
+/// Submits a signed, serialized transaction to the Bitcoin network via the management canister,
+/// returning an error message (rather than trapping the caller's update call) if the broadcast
+/// itself fails.
+pub async fn send_transaction(network: BitcoinNetwork, transaction: Vec<u8>) -> Result<(), String> {
+    let cycles = SEND_TRANSACTION_BASE_CYCLES
+        + (transaction.len() as u64) * SEND_TRANSACTION_PER_BYTE_CYCLES;
+    let request = SendTransactionRequest {
+        transaction,
+        network,
+    };
+
+    bitcoin_send_transaction(request, cycles)
+        .await
+        .map_err(|(_, message)| format!("broadcast failed: {message}"))?;
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+use crate::state;
+use bitcoin::address::NetworkUnchecked;
+use bitcoin::Address;
+use ic_cdk::api::management_canister::bitcoin::BitcoinNetwork;
+use std::str::FromStr;
+
+/// Parses a Bitcoin address and validates it against `network`, the canister's configured
+/// Bitcoin network, returning a human-readable error (rather than silently proceeding) on a
+/// malformed address or a network mismatch (e.g. a testnet address sent to a mainnet canister).
+/// Shared by every entry point that accepts an address as a raw string: the URL-path-based
+/// `/get-balance` handler and the JSON-RPC facade.
+pub fn parse_address(address: &str, network: BitcoinNetwork) -> Result<Address, &'static str> {
+    let address = Address::from_str(address).map_err(|_| "Invalid Bitcoin address")?;
+    require_network(address, network)
+}
+
+/// Validates an already-parsed address against `network`. Request structs that embed
+/// `Address<NetworkUnchecked>` (see `serde_address`) have done the parsing step at deserialize
+/// time; this is the remaining network check.
+pub fn require_network(address: Address<NetworkUnchecked>, network: BitcoinNetwork) -> Result<Address, &'static str> {
+    address
+        .require_network(state::to_bitcoin_network(network))
+        .map_err(|_| "Address does not match the configured network")
+}
+
+/// (De)serializes `bitcoin::Address` by hand rather than relying on the `bitcoin` crate's own
+/// `serde` support, so request/response structs can embed the real address type without pulling
+/// in a crate feature the rest of the canister doesn't otherwise depend on. A request address
+/// deserializes into `Address<NetworkUnchecked>`, since parsing alone can't know which network the
+/// caller meant — callers still run it through `require_network` once the canister's configured
+/// network is in scope.
+pub mod serde_address {
+    use super::{Address, NetworkUnchecked};
+    use serde::de::Error as _;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use std::str::FromStr;
+
+    pub fn serialize<S: Serializer>(address: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&address.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Address<NetworkUnchecked>, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        Address::from_str(&raw).map_err(D::Error::custom)
+    }
+}
+
+/// The management canister reports txids (including outpoint txids within a UTXO) in internal
+/// (reversed) byte order; this returns the bytes in conventional display order.
+pub fn reverse_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut reversed = bytes.to_vec();
+    reversed.reverse();
+    reversed
+}
+
+/// Hex-encodes a txid reported by the management canister, reversing it into display order first.
+pub fn txid_to_hex(txid: &[u8]) -> String {
+    hex::encode(reverse_bytes(txid))
+}
@@ -0,0 +1,121 @@
+use ic_http_certification::utils::add_v2_certificate_header;
+use ic_http_certification::{
+    DefaultCelBuilder, DefaultResponseCertification, HttpCertification, HttpCertificationPath,
+    HttpCertificationTree, HttpCertificationTreeEntry, HttpRequest, HttpResponse,
+    CERTIFICATE_EXPRESSION_HEADER_NAME,
+};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+
+// A certified route: the plain response we hand back on a query call, plus the certification
+// proof that was folded into the canister's certified data / HttpCertificationTree.
+struct CertifiedRoute {
+    response: HttpResponse<'static>,
+    certification: HttpCertification,
+}
+
+// Routes like "/get-balance/<address>" are certified per distinct address, so without a bound
+// the cache would grow by one entry for every address ever queried. Once we're at capacity, the
+// oldest route is evicted to make room for a new one.
+const MAX_ROUTES: usize = 256;
+
+thread_local! {
+    static TREE: RefCell<HttpCertificationTree> = RefCell::new(HttpCertificationTree::default());
+    static ROUTES: RefCell<HashMap<String, CertifiedRoute>> = RefCell::new(HashMap::new());
+    static ROUTE_ORDER: RefCell<VecDeque<String>> = RefCell::new(VecDeque::new());
+}
+
+// Every certified route in this canister uses the same "full certification" CEL expression
+// (certify everything, no header exclusions) since none of our read-only responses vary by
+// request headers.
+fn cel_expression() -> ic_http_certification::DefaultFullCelExpression<'static> {
+    DefaultCelBuilder::full_certification()
+        .with_response_certification(DefaultResponseCertification::response_header_exclusions(
+            vec![],
+        ))
+        .build()
+}
+
+/// Certifies `response` under the exact route `path` (e.g. "/", "/get-p2pkh-address"), replacing
+/// any certification previously registered for the same path, and refreshes the canister's
+/// certified data so the new proof is valid. Call this once a handler has computed a response
+/// worth serving straight off a fast query call.
+pub fn certify(path: &str, mut response: HttpResponse<'static>) {
+    let cel_expr = cel_expression();
+    let request = HttpRequest::builder().with_url(path).build();
+
+    response.add_header((
+        CERTIFICATE_EXPRESSION_HEADER_NAME.to_string(),
+        cel_expr.to_string(),
+    ));
+
+    let certification = HttpCertification::full(&cel_expr, &request, &response, None)
+        .expect("failed to certify response");
+    let tree_path = HttpCertificationPath::exact(path);
+    let is_new_path = !ROUTES.with(|routes| routes.borrow().contains_key(path));
+
+    TREE.with(|tree| {
+        let mut tree = tree.borrow_mut();
+        if let Some(old) = ROUTES.with(|routes| routes.borrow_mut().remove(path)) {
+            tree.delete(&HttpCertificationTreeEntry::new(
+                tree_path.clone(),
+                old.certification,
+            ));
+        }
+
+        if is_new_path {
+            let evicted = ROUTE_ORDER.with(|order| {
+                let mut order = order.borrow_mut();
+                (order.len() >= MAX_ROUTES).then(|| order.pop_front()).flatten()
+            });
+            if let Some(evicted) = evicted {
+                if let Some(old) = ROUTES.with(|routes| routes.borrow_mut().remove(&evicted)) {
+                    tree.delete(&HttpCertificationTreeEntry::new(
+                        HttpCertificationPath::exact(&evicted),
+                        old.certification,
+                    ));
+                }
+            }
+            ROUTE_ORDER.with(|order| order.borrow_mut().push_back(path.to_string()));
+        }
+
+        tree.insert(&HttpCertificationTreeEntry::new(
+            tree_path,
+            certification.clone(),
+        ));
+        ic_cdk::api::set_certified_data(&tree.root_hash());
+    });
+
+    ROUTES.with(|routes| {
+        routes.borrow_mut().insert(
+            path.to_string(),
+            CertifiedRoute {
+                response,
+                certification,
+            },
+        )
+    });
+}
+
+/// Serves the cached, certified response for `path` with a valid `IC-Certificate` header, or
+/// `None` if nothing has been certified for this path yet (the caller should fall back to
+/// upgrading to an update call).
+pub fn serve(path: &str) -> Option<HttpResponse<'static>> {
+    let tree_path = HttpCertificationPath::exact(path);
+
+    let (mut response, witness) = ROUTES.with(|routes| {
+        let routes = routes.borrow();
+        let route = routes.get(path)?;
+        let witness = TREE.with(|tree| {
+            tree.borrow().witness(
+                &HttpCertificationTreeEntry::new(tree_path, route.certification.clone()),
+                path,
+            )
+        });
+        Some((route.response.clone(), witness.ok()?))
+    })?;
+
+    let certificate = ic_cdk::api::data_certificate()?;
+    add_v2_certificate_header(&certificate, &mut response, &witness, path);
+    Some(response)
+}
@@ -0,0 +1,44 @@
+use ic_cdk::api::management_canister::ecdsa::{
+    ecdsa_public_key as mgmt_ecdsa_public_key, sign_with_ecdsa as mgmt_sign_with_ecdsa, EcdsaCurve,
+    EcdsaKeyId, EcdsaPublicKeyArgument, SignWithEcdsaArgument,
+};
+
+/// Returns the SEC1-encoded public key for this canister's threshold ECDSA key, derived along
+/// `derivation_path`, or an error message if the management canister call itself fails (rather
+/// than trapping the caller's update call).
+pub async fn ecdsa_public_key(key_name: String, derivation_path: Vec<Vec<u8>>) -> Result<Vec<u8>, String> {
+    let (response,) = mgmt_ecdsa_public_key(EcdsaPublicKeyArgument {
+        canister_id: None,
+        derivation_path,
+        key_id: EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: key_name,
+        },
+    })
+    .await
+    .map_err(|(_, message)| format!("failed to fetch ECDSA public key: {message}"))?;
+
+    Ok(response.public_key)
+}
+
+/// Signs `message_hash` (must be 32 bytes) with this canister's threshold ECDSA key, derived
+/// along `derivation_path`, returning a 64-byte (r, s) signature, or an error message if the
+/// management canister call itself fails (rather than trapping the caller's update call).
+pub async fn sign_with_ecdsa(
+    key_name: String,
+    derivation_path: Vec<Vec<u8>>,
+    message_hash: Vec<u8>,
+) -> Result<Vec<u8>, String> {
+    let (response,) = mgmt_sign_with_ecdsa(SignWithEcdsaArgument {
+        message_hash,
+        derivation_path,
+        key_id: EcdsaKeyId {
+            curve: EcdsaCurve::Secp256k1,
+            name: key_name,
+        },
+    })
+    .await
+    .map_err(|(_, message)| format!("signing failed: {message}"))?;
+
+    Ok(response.signature)
+}
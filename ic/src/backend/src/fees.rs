@@ -0,0 +1,71 @@
+use crate::bitcoin_api;
+use ic_cdk::api::management_canister::bitcoin::{BitcoinNetwork, MillisatoshiPerByte};
+use serde::Deserialize;
+
+/// A named confirmation-speed target, mapped to a percentile of the current fee distribution
+/// returned by `bitcoin_get_current_fee_percentiles`.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum FeeTarget {
+    Slow,
+    Medium,
+    Fast,
+}
+
+impl FeeTarget {
+    // Index into the 100-element fee percentile vector.
+    fn percentile_index(self) -> usize {
+        match self {
+            FeeTarget::Slow => 24,   // 25th percentile
+            FeeTarget::Medium => 49, // 50th percentile
+            FeeTarget::Fast => 89,   // 90th percentile
+        }
+    }
+}
+
+/// The resolved fee for a named target: the raw percentile and its satoshi/vByte conversion.
+pub struct FeeEstimate {
+    pub fee_per_vbyte: u64,
+    pub millisatoshi_per_byte: MillisatoshiPerByte,
+}
+
+/// Resolves `target` against the canister's current fee percentiles.
+pub async fn estimate(network: BitcoinNetwork, target: FeeTarget) -> FeeEstimate {
+    let percentiles = bitcoin_api::get_current_fee_percentiles(network).await;
+    let millisatoshi_per_byte = percentiles
+        .get(target.percentile_index())
+        .copied()
+        .unwrap_or(1_000);
+
+    FeeEstimate {
+        fee_per_vbyte: millisatoshi_per_byte_to_sat_per_vbyte(millisatoshi_per_byte),
+        millisatoshi_per_byte,
+    }
+}
+
+// Converts millisatoshi/byte to satoshi/vByte, rounding up, with a 1 sat/vByte floor.
+fn millisatoshi_per_byte_to_sat_per_vbyte(millisatoshi_per_byte: MillisatoshiPerByte) -> u64 {
+    ((millisatoshi_per_byte + 999) / 1_000).max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_up_to_the_next_whole_satoshi() {
+        assert_eq!(millisatoshi_per_byte_to_sat_per_vbyte(1_001), 2);
+        assert_eq!(millisatoshi_per_byte_to_sat_per_vbyte(1_999), 2);
+    }
+
+    #[test]
+    fn leaves_an_exact_multiple_unchanged() {
+        assert_eq!(millisatoshi_per_byte_to_sat_per_vbyte(2_000), 2);
+    }
+
+    #[test]
+    fn floors_at_one_sat_per_vbyte() {
+        assert_eq!(millisatoshi_per_byte_to_sat_per_vbyte(0), 1);
+        assert_eq!(millisatoshi_per_byte_to_sat_per_vbyte(1), 1);
+    }
+}
@@ -1,27 +1,37 @@
+mod bitcoin_api;
+mod bitcoin_util;
+mod certification;
+mod ecdsa_api;
+mod fees;
+mod p2pkh;
+mod rpc;
+mod state;
+
+use ic_cdk::api::management_canister::bitcoin::BitcoinNetwork;
 use ic_cdk::*;
 use ic_http_certification::{HttpRequest, HttpResponse, HttpUpdateResponse};
 use serde::{Deserialize, Serialize};
 
 // ---------- Type definitions for API requests & responses ----------
 
-// Request type for get_balance endpoint
-#[derive(Deserialize)]
-pub struct GetBalanceRequestJson {
-    pub address: String,
-}
-
 // Response type for get_balance
 #[derive(Serialize)]
 pub struct GetBalanceResponse {
-    pub address: String,
-    pub balance: f64,
-    pub unit: String,
+    #[serde(with = "bitcoin_util::serde_address")]
+    pub address: bitcoin::Address,
+    pub satoshi: u64,
+    // Fixed-point BTC amount derived from `satoshi` via `bitcoin::Amount`, avoiding the rounding
+    // error a plain `f64` balance would introduce.
+    pub btc: String,
 }
 
 // Request type for get_utxos
 #[derive(Deserialize)]
 pub struct GetUtxosRequestJson {
-    pub address: String,
+    #[serde(deserialize_with = "bitcoin_util::serde_address::deserialize")]
+    pub address: bitcoin::Address<bitcoin::address::NetworkUnchecked>,
+    // Hex-encoded pagination token returned as `next_page` by a previous call.
+    pub page: Option<String>,
 }
 
 // Response type for get_utxos (single UTXO)
@@ -33,25 +43,61 @@ pub struct Utxo {
     pub confirmations: u32,
 }
 
+// Response type for get_utxos (the full page)
+#[derive(Serialize)]
+pub struct GetUtxosResponse {
+    pub utxos: Vec<Utxo>,
+    #[serde(rename = "tipHeight")]
+    pub tip_height: u32,
+    // Hex-encoded pagination token; present when more UTXOs are available.
+    #[serde(rename = "nextPage")]
+    pub next_page: Option<String>,
+}
+
 // Request type for send endpoint
 #[derive(Deserialize)]
 pub struct SendRequestJson {
-    #[serde(rename = "destinationAddress")]
-    pub destination_address: String,
+    #[serde(rename = "destinationAddress", deserialize_with = "bitcoin_util::serde_address::deserialize")]
+    pub destination_address: bitcoin::Address<bitcoin::address::NetworkUnchecked>,
     #[serde(rename = "amountInSatoshi")]
     pub amount_in_satoshi: u64,
+    // Named confirmation-speed target; defaults to "medium" when omitted.
+    #[serde(rename = "feeTarget", default)]
+    pub fee_target: Option<fees::FeeTarget>,
+}
+
+// Request type for estimate-fee endpoint
+#[derive(Deserialize)]
+pub struct EstimateFeeRequestJson {
+    pub target: fees::FeeTarget,
+}
+
+// Response type for estimate-fee
+#[derive(Serialize)]
+pub struct EstimateFeeResponse {
+    #[serde(rename = "feePerVbyte")]
+    pub fee_per_vbyte: u64,
+    #[serde(rename = "millisatoshiPerByte")]
+    pub millisatoshi_per_byte: u64,
 }
 
 // Response type for send
 #[derive(Serialize)]
 pub struct SendResponse {
     pub success: bool,
-    pub destination: String,
+    #[serde(with = "bitcoin_util::serde_address")]
+    pub destination: bitcoin::Address,
     pub amount: u64,
     #[serde(rename = "txId")]
     pub tx_id: String,
 }
 
+// Response type for a 400 error
+#[derive(Serialize)]
+pub struct ErrorResponse<'a> {
+    pub error: &'a str,
+}
+
 // Response type for welcome message
 #[derive(Serialize)]
 pub struct WelcomeMessage {
@@ -61,7 +107,8 @@ pub struct WelcomeMessage {
 // Response type for get_p2pkh_address
 #[derive(Serialize)]
 pub struct GetP2pkhAddressResponse {
-    pub address: String,
+    #[serde(with = "bitcoin_util::serde_address")]
+    pub address: bitcoin::Address,
 }
 
 // Response type for dummy_test
@@ -88,30 +135,87 @@ pub struct TestData {
     pub is_test: bool,
 }
 
+// ---------- Canister lifecycle ----------
+
+// Configures the Bitcoin network and threshold ECDSA key this canister uses.
+#[init]
+fn init(network: BitcoinNetwork, ecdsa_key_name: String) {
+    state::set_network(network);
+    state::set_ecdsa_key_name(ecdsa_key_name);
+
+    // The welcome message is static, so it can be certified immediately rather than waiting for
+    // the first update call to populate it.
+    certified_json_response("/", &welcome_message());
+}
+
 // ---------- HTTP query & update entry points ----------
 
-// Handles GET / OPTIONS and returns an upgradeable response
+// Serves certified routes (welcome, the P2PKH address, and the last-fetched balance/fee
+// snapshots) directly from a fast query call when we have one cached, and upgrades everything
+// else (UTXOs, sends, fee estimates, and any route we haven't certified yet) to an update call.
 #[query]
-fn http_request(_req: HttpRequest) -> HttpResponse<'static> {
-    HttpResponse::builder().with_upgrade(true).build()
+fn http_request(req: HttpRequest) -> HttpResponse<'static> {
+    let url = req.url();
+
+    if url.contains("/get-utxos")
+        || url.contains("/send")
+        || url.contains("/estimate-fee")
+        || url.contains("/rpc")
+        || url.contains("/dummy-test")
+    {
+        return HttpResponse::builder().with_upgrade(true).build();
+    }
+
+    let cached = if url.contains("/get-p2pkh-address") {
+        certification::serve("/get-p2pkh-address")
+    } else if url.contains("/get-current-fee-percentiles") {
+        certification::serve("/get-current-fee-percentiles")
+    } else if url.contains("/get-balance") {
+        // The balance is certified under the literal request URL (address as a path segment,
+        // min_confirmations as the query string it already was) rather than a key derived from
+        // the request body: the v2 certificate the gateway checks is validated against the real
+        // request URL, so anything else would be a witness computed for a path the client never
+        // actually sent.
+        parse_balance_path(url).and_then(|address| {
+            let min_confirmations = query_param(url, "min_confirmations").and_then(|v| v.parse().ok());
+            certification::serve(&balance_cache_key(address, min_confirmations))
+        })
+    } else if is_root_path(url) {
+        certification::serve("/")
+    } else {
+        None
+    };
+
+    cached.unwrap_or_else(|| HttpResponse::builder().with_upgrade(true).build())
+}
+
+// Only an exact "/" (ignoring a query string) should fall back to the cached welcome message;
+// anything else unmatched (e.g. a stray "/favicon.ico") must upgrade rather than be served the
+// witness computed for a different path.
+fn is_root_path(url: &str) -> bool {
+    matches!(url.split('?').next(), Some("" | "/"))
 }
 
 // Handles POST routes and routes to specific handlers
 #[update]
-fn http_request_update(req: HttpRequest) -> HttpUpdateResponse<'static> {
+async fn http_request_update(req: HttpRequest) -> HttpUpdateResponse<'static> {
     let url = req.url();
-    
+
     // Simple routing based on URL content
     if url.contains("/get-balance") { //
-        handle_get_balance(req)
+        handle_get_balance(req).await
     } else if url.contains("/get-utxos") { //
-        handle_get_utxos(req)
+        handle_get_utxos(req).await
     } else if url.contains("/get-current-fee-percentiles") { //
-        handle_get_fee_percentiles()
+        handle_get_fee_percentiles(req).await
+    } else if url.contains("/estimate-fee") {
+        handle_estimate_fee(req).await
     } else if url.contains("/get-p2pkh-address") {
-        handle_get_p2pkh_address()
+        handle_get_p2pkh_address().await
     } else if url.contains("/send") { //
-        handle_send(req)
+        handle_send(req).await
+    } else if url.contains("/rpc") {
+        handle_rpc(req).await
     } else if url.contains("/dummy-test") {
         handle_dummy_test()
     } else {
@@ -123,20 +227,106 @@ fn http_request_update(req: HttpRequest) -> HttpUpdateResponse<'static> {
 
 // Welcome message
 fn handle_welcome() -> HttpUpdateResponse<'static> {
-    let welcome = WelcomeMessage {
+    certified_json_response("/", &welcome_message())
+}
+
+fn welcome_message() -> WelcomeMessage {
+    WelcomeMessage {
         message: "Welcome to the Dummy Bitcoin Canister API".to_string(),
+    }
+}
+
+// Returns the balance of a given Bitcoin address, in satoshi, as reported by the management
+// canister's Bitcoin integration. The address is taken from the request path itself —
+// `/get-balance/<address>` — rather than the request body, so the literal URL a client sends is
+// exactly what gets certified; `min_confirmations` stays a query parameter, since that part of
+// the URL was already real.
+async fn handle_get_balance(req: HttpRequest) -> HttpUpdateResponse<'static> {
+    let url = req.url();
+    let Some(address) = parse_balance_path(url) else {
+        return error_response("expected /get-balance/<address>");
+    };
+    let min_confirmations = query_param(url, "min_confirmations").and_then(|v| v.parse().ok());
+
+    let network = state::network();
+    let address = match parse_address(address, network) {
+        Ok(address) => address,
+        Err(response) => return response,
     };
-    json_response(&welcome)
+
+    let satoshi = bitcoin_api::get_balance(network, address.to_string(), min_confirmations).await;
+    let cache_key = balance_cache_key(&address.to_string(), min_confirmations);
+    let response = GetBalanceResponse {
+        address,
+        satoshi,
+        btc: bitcoin::Amount::from_sat(satoshi).to_string(),
+    };
+    certified_json_response(&cache_key, &response)
+}
+
+// Parses the address segment out of a `/get-balance/<address>` request path.
+fn parse_balance_path(url: &str) -> Option<&str> {
+    let rest = url.strip_prefix("/get-balance/")?;
+    let address = rest.split('?').next().unwrap_or(rest);
+    (!address.is_empty()).then_some(address)
+}
+
+// Looks up a query-string parameter on a request URL, e.g. `min_confirmations` in
+// `/get-balance/<address>?min_confirmations=6`.
+fn query_param<'a>(url: &'a str, key: &str) -> Option<&'a str> {
+    let query = url.split_once('?')?.1;
+    query.split('&').find_map(|pair| {
+        let (k, v) = pair.split_once('=')?;
+        (k == key).then_some(v)
+    })
 }
 
-// Dummy: Returns the balance of a given Bitcoin address
-fn handle_get_balance(req: HttpRequest) -> HttpUpdateResponse<'static> {
-    match serde_json::from_slice::<GetBalanceRequestJson>(req.body()) {
+// The balance snapshot is certified under the literal URL a client requests it at, so the same
+// key is used both when certifying the response and when a later query call re-derives it from
+// the incoming request's own URL.
+fn balance_cache_key(address: &str, min_confirmations: Option<u32>) -> String {
+    match min_confirmations {
+        Some(min_confirmations) => format!("/get-balance/{address}?min_confirmations={min_confirmations}"),
+        None => format!("/get-balance/{address}"),
+    }
+}
+
+// Returns a page of UTXOs of a given Bitcoin address, as reported by the management canister's
+// Bitcoin integration.
+async fn handle_get_utxos(req: HttpRequest) -> HttpUpdateResponse<'static> {
+    match serde_json::from_slice::<GetUtxosRequestJson>(req.body()) {
         Ok(request) => {
-            let response = GetBalanceResponse {
-                address: request.address,
-                balance: 0.005,
-                unit: "BTC".to_string(),
+            let network = state::network();
+            let address = match require_network(request.address, network) {
+                Ok(address) => address,
+                Err(response) => return response,
+            };
+            let page = match request.page {
+                Some(page) => match hex::decode(page) {
+                    Ok(page) => Some(page),
+                    Err(_) => return error_response("Invalid page token"),
+                },
+                None => None,
+            };
+            let result = match bitcoin_api::get_utxos(network, address.to_string(), page).await {
+                Ok(result) => result,
+                Err(message) => return error_response(&message),
+            };
+            let tip_height = result.tip_height;
+            let utxos = result
+                .utxos
+                .into_iter()
+                .map(|utxo| Utxo {
+                    txid: bitcoin_util::txid_to_hex(&utxo.outpoint.txid),
+                    vout: utxo.outpoint.vout,
+                    value: utxo.value,
+                    confirmations: tip_height.saturating_sub(utxo.height) + 1,
+                })
+                .collect();
+            let response = GetUtxosResponse {
+                utxos,
+                tip_height,
+                next_page: result.next_page.map(hex::encode),
             };
             json_response(&response)
         }
@@ -144,60 +334,87 @@ fn handle_get_balance(req: HttpRequest) -> HttpUpdateResponse<'static> {
     }
 }
 
-// Dummy: Returns the UTXOs of a given Bitcoin address
-fn handle_get_utxos(req: HttpRequest) -> HttpUpdateResponse<'static> {
-    match serde_json::from_slice::<GetUtxosRequestJson>(req.body()) {
-        Ok(_request) => {
-            let utxos = vec![
-                Utxo {
-                    txid: "dummy-txid-1".to_string(),
-                    vout: 0,
-                    value: 25000,
-                    confirmations: 5,
-                },
-                Utxo {
-                    txid: "dummy-txid-2".to_string(),
-                    vout: 1,
-                    value: 50000,
-                    confirmations: 3,
-                },
-            ];
-            json_response(&utxos)
+// Returns the 100 fee percentiles measured in millisatoshi/byte, as reported by the management
+// canister's Bitcoin integration.
+async fn handle_get_fee_percentiles(_req: HttpRequest) -> HttpUpdateResponse<'static> {
+    let fees = bitcoin_api::get_current_fee_percentiles(state::network()).await;
+    certified_json_response("/get-current-fee-percentiles", &fees)
+}
+
+// Resolves a named confirmation-speed target into a sat/vByte fee rate.
+async fn handle_estimate_fee(req: HttpRequest) -> HttpUpdateResponse<'static> {
+    match serde_json::from_slice::<EstimateFeeRequestJson>(req.body()) {
+        Ok(request) => {
+            let estimate = fees::estimate(state::network(), request.target).await;
+            let response = EstimateFeeResponse {
+                fee_per_vbyte: estimate.fee_per_vbyte,
+                millisatoshi_per_byte: estimate.millisatoshi_per_byte,
+            };
+            json_response(&response)
         }
         Err(_) => error_response("Invalid request body")
     }
 }
 
-// Dummy: Returns the 100 fee percentiles measured in millisatoshi/byte
-fn handle_get_fee_percentiles() -> HttpUpdateResponse<'static> {
-    let fees: Vec<u64> = (100..200).collect();
-    json_response(&fees)
-}
-
-// Dummy: Returns the P2PKH address of this canister
-fn handle_get_p2pkh_address() -> HttpUpdateResponse<'static> {
-    let response = GetP2pkhAddressResponse {
-        address: "tb1qdummyaddressxyz1234567890".to_string(),
+// Returns the P2PKH address this canister controls via threshold ECDSA.
+async fn handle_get_p2pkh_address() -> HttpUpdateResponse<'static> {
+    let address = match p2pkh::get_address(state::network(), state::ecdsa_key_name()).await {
+        Ok(address) => address,
+        Err(message) => return error_response(&message),
     };
-    json_response(&response)
+    let response = GetP2pkhAddressResponse { address };
+    certified_json_response("/get-p2pkh-address", &response)
 }
 
-// Dummy: Sends satoshis from this canister to a specified address
-fn handle_send(req: HttpRequest) -> HttpUpdateResponse<'static> {
+// Builds, signs (via threshold ECDSA), and broadcasts a P2PKH transaction sending satoshis from
+// this canister's wallet to a destination address.
+async fn handle_send(req: HttpRequest) -> HttpUpdateResponse<'static> {
     match serde_json::from_slice::<SendRequestJson>(req.body()) {
         Ok(request) => {
-            let response = SendResponse {
-                success: true,
-                destination: request.destination_address,
-                amount: request.amount_in_satoshi,
-                tx_id: "dummy-txid-sent-1234567890".to_string(),
+            let network = state::network();
+            let dst_address = match require_network(request.destination_address, network) {
+                Ok(address) => address,
+                Err(response) => return response,
             };
-            json_response(&response)
+            if request.amount_in_satoshi < p2pkh::DUST_THRESHOLD {
+                return error_response("amountInSatoshi is below the dust threshold");
+            }
+
+            let fee_target = request.fee_target.unwrap_or(fees::FeeTarget::Medium);
+            let fee_per_vbyte = fees::estimate(network, fee_target).await.fee_per_vbyte;
+
+            match p2pkh::send(
+                network,
+                state::ecdsa_key_name(),
+                &dst_address,
+                request.amount_in_satoshi,
+                fee_per_vbyte,
+            )
+            .await
+            {
+                Ok(tx_id) => {
+                    let response = SendResponse {
+                        success: true,
+                        destination: dst_address,
+                        amount: request.amount_in_satoshi,
+                        tx_id: tx_id.to_string(),
+                    };
+                    json_response(&response)
+                }
+                Err(message) => error_response(&message),
+            }
         }
         Err(_) => error_response("Invalid request body")
     }
 }
 
+// Bitcoin Core JSON-RPC compatibility facade: accepts a `{ jsonrpc, id, method, params }`
+// envelope and dispatches it onto the canister's existing Bitcoin logic.
+async fn handle_rpc(req: HttpRequest) -> HttpUpdateResponse<'static> {
+    let response = rpc::handle(req.body()).await;
+    json_response(&response)
+}
+
 // Dummy test endpoint
 fn handle_dummy_test() -> HttpUpdateResponse<'static> {
     let response = DummyTestResponse {
@@ -225,9 +442,38 @@ fn json_response<T: Serialize>(data: &T) -> HttpUpdateResponse<'static> {
         .build_update()
 }
 
-// Builds an error JSON response
+// Builds a JSON response, certifies it under `path` so a later query call can serve it directly,
+// and returns the update-call response.
+fn certified_json_response<T: Serialize>(path: &str, data: &T) -> HttpUpdateResponse<'static> {
+    let body = serde_json::to_vec(data).unwrap_or_else(|_| b"{}".to_vec());
+    certification::certify(path, HttpResponse::builder().with_body(body.clone()).build());
+    HttpResponse::builder().with_body(body).build_update()
+}
+
+// Builds a 400 error JSON response. Goes through `serde_json` (rather than a hand-rolled format
+// string) since some error messages — e.g. a broadcast-rejection reason relayed verbatim from the
+// management canister — are not under our control and may contain characters that would otherwise
+// produce invalid JSON.
 fn error_response(message: &str) -> HttpUpdateResponse<'static> {
+    let body = serde_json::to_vec(&ErrorResponse { error: message }).unwrap_or_else(|_| b"{}".to_vec());
     HttpResponse::builder()
-        .with_body(format!(r#"{{"error":"{}"}}"#, message).into_bytes())
+        .with_status_code(400)
+        .with_body(body)
         .build_update()
-}
\ No newline at end of file
+}
+
+// Parses a Bitcoin address and validates it against the canister's configured network, turning
+// a parse/network-mismatch error into a 400 response.
+fn parse_address(address: &str, network: BitcoinNetwork) -> Result<bitcoin::Address, HttpUpdateResponse<'static>> {
+    bitcoin_util::parse_address(address, network).map_err(error_response)
+}
+
+// Validates an address already parsed by a request struct's `serde_address::deserialize` against
+// the canister's configured network, turning a network-mismatch error into a 400 response.
+fn require_network(
+    address: bitcoin::Address<bitcoin::address::NetworkUnchecked>,
+    network: BitcoinNetwork,
+) -> Result<bitcoin::Address, HttpUpdateResponse<'static>> {
+    bitcoin_util::require_network(address, network).map_err(error_response)
+}
+
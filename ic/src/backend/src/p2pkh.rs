@@ -0,0 +1,285 @@
+use crate::{bitcoin_api, ecdsa_api, state};
+use bitcoin::hashes::Hash;
+use bitcoin::script::{Builder, PushBytesBuf};
+use bitcoin::sighash::{EcdsaSighashType, SighashCache};
+use bitcoin::{
+    absolute::LockTime, transaction::Version, Address, Amount, OutPoint, PublicKey, ScriptBuf,
+    Sequence, Transaction, TxIn, TxOut, Txid, Witness,
+};
+use futures::future::join_all;
+use ic_cdk::api::management_canister::bitcoin::{BitcoinNetwork, Satoshi, Utxo};
+
+/// The smallest amount (in satoshi) worth creating an output for; a change output below this is
+/// folded into the miner fee instead, and a requested send amount below this is rejected outright.
+pub(crate) const DUST_THRESHOLD: Satoshi = 1_000;
+
+/// Conservative size, in bytes, of a signed P2PKH scriptSig: a push of a ~72-byte DER signature
+/// plus sighash-type byte, and a push of a 33-byte compressed public key.
+const P2PKH_SIGNATURE_SIZE: u64 = 1 + 72 + 1 + 1 + 33;
+
+/// Upper bound on the fee-estimation loop in `build_transaction`, guarding against the change
+/// output oscillating across `DUST_THRESHOLD` forever instead of converging.
+const MAX_FEE_ITERATIONS: u32 = 10;
+
+/// Derives this canister's P2PKH Bitcoin address from its threshold ECDSA public key.
+pub async fn get_address(network: BitcoinNetwork, key_name: String) -> Result<Address, String> {
+    let public_key = ecdsa_api::ecdsa_public_key(key_name, state::derivation_path()).await?;
+    Ok(address_from_public_key(network, &public_key))
+}
+
+fn address_from_public_key(network: BitcoinNetwork, public_key: &[u8]) -> Address {
+    let public_key = PublicKey::from_slice(public_key).expect("invalid public key from management canister");
+    Address::p2pkh(&public_key, state::to_bitcoin_network(network))
+}
+
+/// Builds, signs, and broadcasts a P2PKH transaction paying `amount` satoshi to `dst_address`
+/// from this canister's wallet, using `fee_per_vbyte` (satoshi/vByte) to size the miner fee.
+/// Returns the txid of the broadcast transaction.
+pub async fn send(
+    network: BitcoinNetwork,
+    key_name: String,
+    dst_address: &Address,
+    amount: Satoshi,
+    fee_per_vbyte: u64,
+) -> Result<Txid, String> {
+    let derivation_path = state::derivation_path();
+    let own_public_key = ecdsa_api::ecdsa_public_key(key_name.clone(), derivation_path.clone()).await?;
+    let own_address = address_from_public_key(network, &own_public_key);
+
+    let utxos = bitcoin_api::get_utxos(network, own_address.to_string(), None)
+        .await?
+        .utxos;
+
+    let (tx, selected_count) = build_transaction(&own_address, dst_address, &utxos, amount, fee_per_vbyte)?;
+
+    // Every input's sighash depends only on the unsigned transaction, so compute them all up
+    // front and sign them concurrently — each threshold ECDSA signature is a separate
+    // inter-canister call, and signing inputs one at a time would serialize their latency.
+    let sighashes = (0..selected_count)
+        .map(|i| {
+            SighashCache::new(&tx)
+                .legacy_signature_hash(i, &own_address.script_pubkey(), EcdsaSighashType::All.to_u32())
+                .map(|sighash| sighash.as_byte_array().to_vec())
+                .map_err(|e| format!("failed to compute sighash for input {i}: {e}"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let signatures = join_all(sighashes.into_iter().map(|sighash| {
+        ecdsa_api::sign_with_ecdsa(key_name.clone(), derivation_path.clone(), sighash)
+    }))
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let mut signed_tx = tx;
+    for (i, raw_signature) in signatures.into_iter().enumerate() {
+        let der_signature = sec1_to_der(&raw_signature)
+            .map_err(|e| format!("failed to sign input {i}: {e}"))?;
+        let mut sig_with_hashtype = der_signature;
+        sig_with_hashtype.push(EcdsaSighashType::All.to_u32() as u8);
+
+        signed_tx.input[i].script_sig = Builder::new()
+            .push_slice(PushBytesBuf::try_from(sig_with_hashtype).expect("signature too long"))
+            .push_slice(PushBytesBuf::try_from(own_public_key.clone()).expect("public key too long"))
+            .into_script();
+    }
+
+    let raw_tx = bitcoin::consensus::encode::serialize(&signed_tx);
+    bitcoin_api::send_transaction(network, raw_tx).await?;
+
+    Ok(signed_tx.compute_txid())
+}
+
+/// Selects UTXOs (largest-first) and builds an unsigned transaction covering `amount` plus an
+/// estimated fee, iterating the fee estimate until it stabilizes against the actual input/output
+/// count. Returns the transaction and the number of inputs selected.
+fn build_transaction(
+    own_address: &Address,
+    dst_address: &Address,
+    utxos: &[Utxo],
+    amount: Satoshi,
+    fee_per_vbyte: u64,
+) -> Result<(Transaction, usize), String> {
+    let mut sorted_utxos: Vec<&Utxo> = utxos.iter().collect();
+    sorted_utxos.sort_by(|a, b| b.value.cmp(&a.value));
+
+    // Builds the candidate transaction for a given fee, plus the vsize-derived fee it implies.
+    let build_at_fee = |fee: Satoshi| -> Result<(Transaction, usize, Satoshi), String> {
+        let (selected, total) = select_utxos(&sorted_utxos, amount, fee)?;
+        let change = total - amount - fee;
+        let num_outputs = if change >= DUST_THRESHOLD { 2 } else { 1 };
+
+        let tx = unsigned_transaction(own_address, dst_address, &selected, amount, change, num_outputs == 2);
+        let estimated_vsize = tx.vsize() as u64 + selected.len() as u64 * P2PKH_SIGNATURE_SIZE;
+        Ok((tx, selected.len(), estimated_vsize * fee_per_vbyte))
+    };
+
+    let mut fee = 0;
+    let mut prev_fee = None;
+    for _ in 0..MAX_FEE_ITERATIONS {
+        let (tx, selected_count, new_fee) = build_at_fee(fee)?;
+
+        if new_fee == fee {
+            return Ok((tx, selected_count));
+        }
+
+        // A change output straddling `DUST_THRESHOLD` can flip the output count (and so the fee)
+        // back and forth between the same two values without ever settling. Once that happens,
+        // rebuild once more at the larger of the two fees — enough to cover either output shape —
+        // instead of looping until the canister's instruction limit traps the call.
+        if prev_fee == Some(new_fee) {
+            let (tx, selected_count, _) = build_at_fee(fee.max(new_fee))?;
+            return Ok((tx, selected_count));
+        }
+
+        prev_fee = Some(fee);
+        fee = new_fee;
+    }
+
+    Err("failed to converge on a transaction fee".to_string())
+}
+
+/// Greedily accumulates UTXOs (largest-first) until their total covers `amount + fee`.
+fn select_utxos<'a>(
+    sorted_utxos: &[&'a Utxo],
+    amount: Satoshi,
+    fee: Satoshi,
+) -> Result<(Vec<&'a Utxo>, Satoshi), String> {
+    let mut selected = Vec::new();
+    let mut total = 0;
+    for utxo in sorted_utxos {
+        if total >= amount + fee {
+            break;
+        }
+        total += utxo.value;
+        selected.push(*utxo);
+    }
+
+    if total < amount + fee {
+        return Err(format!(
+            "insufficient funds: need {} satoshi but wallet only holds {} satoshi",
+            amount + fee,
+            total
+        ));
+    }
+
+    Ok((selected, total))
+}
+
+fn unsigned_transaction(
+    own_address: &Address,
+    dst_address: &Address,
+    selected: &[&Utxo],
+    amount: Satoshi,
+    change: Satoshi,
+    with_change_output: bool,
+) -> Transaction {
+    let input = selected
+        .iter()
+        .map(|utxo| TxIn {
+            previous_output: OutPoint {
+                txid: outpoint_txid(&utxo.outpoint.txid),
+                vout: utxo.outpoint.vout,
+            },
+            script_sig: ScriptBuf::new(),
+            sequence: Sequence::MAX,
+            witness: Witness::new(),
+        })
+        .collect();
+
+    let mut output = vec![TxOut {
+        value: Amount::from_sat(amount),
+        script_pubkey: dst_address.script_pubkey(),
+    }];
+    if with_change_output {
+        output.push(TxOut {
+            value: Amount::from_sat(change),
+            script_pubkey: own_address.script_pubkey(),
+        });
+    }
+
+    Transaction {
+        version: Version::ONE,
+        lock_time: LockTime::ZERO,
+        input,
+        output,
+    }
+}
+
+// The management canister already reports `utxo.outpoint.txid` in the internal byte order
+// `Txid::from_slice` expects; only display (hex) output needs `bitcoin_util::reverse_bytes` (see
+// `bitcoin_util::txid_to_hex`). Reversing here as well would build the `OutPoint` from the wrong
+// txid, so every spend would reference a nonexistent previous output.
+fn outpoint_txid(bytes: &[u8]) -> Txid {
+    Txid::from_slice(bytes).expect("management canister returned an invalid txid")
+}
+
+// Converts a 64-byte (r, s) threshold ECDSA signature into DER encoding, as required by Bitcoin
+// script. `sign_with_ecdsa` can return a high-S signature, which BIP146 relay/standardness rules
+// reject, so normalize to low-S first or roughly half of our broadcasts would be dropped as
+// non-standard.
+fn sec1_to_der(signature: &[u8]) -> Result<Vec<u8>, String> {
+    let mut sig = bitcoin::secp256k1::ecdsa::Signature::from_compact(signature).map_err(|e| e.to_string())?;
+    sig.normalize_s();
+    Ok(sig.serialize_der().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_cdk::api::management_canister::bitcoin::Outpoint;
+
+    // secp256k1's generator point, compressed — an arbitrary but valid public key for exercising
+    // address/transaction construction in tests.
+    const TEST_PUBLIC_KEY_HEX: &str =
+        "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798";
+
+    fn test_address() -> Address {
+        let public_key = hex::decode(TEST_PUBLIC_KEY_HEX).unwrap();
+        address_from_public_key(BitcoinNetwork::Testnet, &public_key)
+    }
+
+    fn utxo(value: Satoshi) -> Utxo {
+        Utxo {
+            outpoint: Outpoint {
+                txid: vec![0; 32],
+                vout: 0,
+            },
+            value,
+            height: 0,
+        }
+    }
+
+    #[test]
+    fn selects_the_fewest_largest_utxos_that_cover_amount_plus_fee() {
+        let own = test_address();
+        let dst = test_address();
+        let utxos = vec![utxo(1_000), utxo(5_000), utxo(10_000)];
+
+        let (tx, selected_count) = build_transaction(&own, &dst, &utxos, 3_000, 1).unwrap();
+
+        // The 10_000-satoshi UTXO alone covers the amount plus fee, so the smaller ones are left
+        // unspent and a change output is created for the remainder.
+        assert_eq!(selected_count, 1);
+        assert_eq!(tx.input.len(), 1);
+        assert_eq!(tx.output.len(), 2);
+    }
+
+    #[test]
+    fn converges_when_the_change_straddles_the_dust_threshold() {
+        let own = test_address();
+        let dst = test_address();
+        // With a single 10_000-satoshi input, the 1_200-satoshi gap between `amount` and the
+        // input value straddles `DUST_THRESHOLD`: a 2-output fee estimate drops the change below
+        // the threshold, and the resulting 1-output estimate pushes it back above — the exact
+        // oscillation `MAX_FEE_ITERATIONS`/`prev_fee` are meant to break out of.
+        let utxos = vec![utxo(10_000)];
+
+        let (tx, selected_count) = build_transaction(&own, &dst, &utxos, 8_800, 1).unwrap();
+
+        assert_eq!(selected_count, 1);
+        // The oscillation is broken by settling on the higher of the two fees, which leaves the
+        // change below the dust threshold and folds it into the fee instead of a change output.
+        assert_eq!(tx.output.len(), 1);
+    }
+}
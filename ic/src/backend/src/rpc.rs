@@ -0,0 +1,211 @@
+use crate::{bitcoin_api, bitcoin_util, fees, p2pkh, state};
+use ic_cdk::api::management_canister::bitcoin::BitcoinNetwork;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+// Bitcoin Core JSON-RPC error codes (see bitcoind's rpc/protocol.h); we only need a handful.
+const RPC_MISC_ERROR: i32 = -1;
+const RPC_WALLET_INSUFFICIENT_FUNDS: i32 = -6;
+const RPC_INVALID_PARAMETER: i32 = -8;
+const RPC_METHOD_NOT_FOUND: i32 = -32601;
+const RPC_PARSE_ERROR: i32 = -32700;
+
+#[derive(Deserialize)]
+pub struct RpcRequest {
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Vec<Value>,
+}
+
+#[derive(Serialize)]
+pub struct RpcResponse {
+    pub result: Option<Value>,
+    pub error: Option<RpcError>,
+    pub id: Value,
+}
+
+#[derive(Serialize)]
+pub struct RpcError {
+    pub code: i32,
+    pub message: String,
+}
+
+/// Parses a Bitcoin Core-style JSON-RPC envelope and dispatches it to the canister's existing
+/// Bitcoin logic, returning a `{ "result", "error", "id" }` envelope in reply.
+pub async fn handle(body: &[u8]) -> RpcResponse {
+    let request: RpcRequest = match serde_json::from_slice(body) {
+        Ok(request) => request,
+        Err(_) => {
+            return RpcResponse {
+                result: None,
+                error: Some(RpcError {
+                    code: RPC_PARSE_ERROR,
+                    message: "Parse error".to_string(),
+                }),
+                id: Value::Null,
+            }
+        }
+    };
+
+    let id = request.id.clone();
+    match execute(&request).await {
+        Ok(result) => RpcResponse {
+            result: Some(result),
+            error: None,
+            id,
+        },
+        Err(error) => RpcResponse {
+            result: None,
+            error: Some(error),
+            id,
+        },
+    }
+}
+
+async fn execute(request: &RpcRequest) -> Result<Value, RpcError> {
+    let network = state::network();
+
+    match request.method.as_str() {
+        // getnewaddress: this canister has a single wallet, so every call returns the same
+        // threshold-ECDSA-controlled P2PKH address.
+        "getnewaddress" => {
+            let address = p2pkh::get_address(network, state::ecdsa_key_name())
+                .await
+                .map_err(misc_error)?;
+            Ok(Value::String(address.to_string()))
+        }
+
+        // getbalance/scantxoutset: unlike Core, our wallet is a single address, so both take
+        // that address as their first parameter and return its balance in BTC.
+        "getbalance" | "scantxoutset" => {
+            let address = parse_address(&param_str(request, 0)?, network)?;
+            let satoshi = bitcoin_api::get_balance(network, address.to_string(), None).await;
+            Ok(json!(bitcoin::Amount::from_sat(satoshi).to_btc()))
+        }
+
+        // listunspent: takes the wallet address as its first parameter (Core scopes by the
+        // loaded wallet instead) and returns its UTXO set in Core's listunspent shape.
+        "listunspent" => {
+            let address = parse_address(&param_str(request, 0)?, network)?;
+            let response = bitcoin_api::get_utxos(network, address.to_string(), None)
+                .await
+                .map_err(misc_error)?;
+            let tip_height = response.tip_height;
+            let entries: Vec<Value> = response
+                .utxos
+                .into_iter()
+                .map(|utxo| {
+                    json!({
+                        "txid": bitcoin_util::txid_to_hex(&utxo.outpoint.txid),
+                        "vout": utxo.outpoint.vout,
+                        "address": address.to_string(),
+                        "amount": bitcoin::Amount::from_sat(utxo.value).to_btc(),
+                        "confirmations": tip_height.saturating_sub(utxo.height) + 1,
+                        "spendable": true,
+                    })
+                })
+                .collect();
+            Ok(Value::Array(entries))
+        }
+
+        // estimatesmartfee: takes a confirmation target in blocks and maps it onto our three
+        // named fee targets, returning Core's `{ feerate (BTC/kvB), blocks }` shape.
+        "estimatesmartfee" => {
+            let conf_target = param_f64(request, 0)? as u32;
+            let target = if conf_target <= 2 {
+                fees::FeeTarget::Fast
+            } else if conf_target <= 6 {
+                fees::FeeTarget::Medium
+            } else {
+                fees::FeeTarget::Slow
+            };
+            let estimate = fees::estimate(network, target).await;
+            let btc_per_kvb = estimate.fee_per_vbyte as f64 * 1000.0 / 100_000_000.0;
+            Ok(json!({ "feerate": btc_per_kvb, "blocks": conf_target }))
+        }
+
+        // createrawtransaction/sendrawtransaction: this canister only ever builds and signs its
+        // own transactions, so both collapse onto the same build-sign-broadcast path, taking a
+        // destination address and a BTC amount rather than Core's raw input/output arrays.
+        "createrawtransaction" | "sendrawtransaction" => {
+            let destination = parse_address(&param_str(request, 0)?, network)?;
+            let amount_btc = param_f64(request, 1)?;
+            let amount_satoshi = bitcoin::Amount::from_btc(amount_btc)
+                .map_err(|e| invalid_params(e.to_string()))?
+                .to_sat();
+            if amount_satoshi < p2pkh::DUST_THRESHOLD {
+                return Err(invalid_params("amount is below the dust threshold"));
+            }
+
+            let fee_per_vbyte = fees::estimate(network, fees::FeeTarget::Medium)
+                .await
+                .fee_per_vbyte;
+            let tx_id = p2pkh::send(
+                network,
+                state::ecdsa_key_name(),
+                &destination,
+                amount_satoshi,
+                fee_per_vbyte,
+            )
+            .await
+            .map_err(wallet_error)?;
+            Ok(Value::String(tx_id.to_string()))
+        }
+
+        other => Err(method_not_found(other)),
+    }
+}
+
+fn parse_address(address: &str, network: BitcoinNetwork) -> Result<bitcoin::Address, RpcError> {
+    bitcoin_util::parse_address(address, network).map_err(invalid_params)
+}
+
+fn param_str(request: &RpcRequest, index: usize) -> Result<String, RpcError> {
+    request
+        .params
+        .get(index)
+        .and_then(Value::as_str)
+        .map(str::to_string)
+        .ok_or_else(|| invalid_params(format!("missing or invalid string parameter at index {index}")))
+}
+
+fn param_f64(request: &RpcRequest, index: usize) -> Result<f64, RpcError> {
+    request
+        .params
+        .get(index)
+        .and_then(Value::as_f64)
+        .ok_or_else(|| invalid_params(format!("missing or invalid numeric parameter at index {index}")))
+}
+
+fn invalid_params(message: impl Into<String>) -> RpcError {
+    RpcError {
+        code: RPC_INVALID_PARAMETER,
+        message: message.into(),
+    }
+}
+
+// A failed inter-canister call (fetching the ECDSA public key or the UTXO set), as opposed to a
+// malformed request or an application-level wallet error.
+fn misc_error(message: String) -> RpcError {
+    RpcError {
+        code: RPC_MISC_ERROR,
+        message,
+    }
+}
+
+fn method_not_found(method: &str) -> RpcError {
+    RpcError {
+        code: RPC_METHOD_NOT_FOUND,
+        message: format!("Method not found: {method}"),
+    }
+}
+
+fn wallet_error(message: String) -> RpcError {
+    let code = if message.contains("insufficient funds") {
+        RPC_WALLET_INSUFFICIENT_FUNDS
+    } else {
+        RPC_MISC_ERROR
+    };
+    RpcError { code, message }
+}
@@ -0,0 +1,46 @@
+use ic_cdk::api::management_canister::bitcoin::BitcoinNetwork;
+use std::cell::RefCell;
+
+thread_local! {
+    // The Bitcoin network (mainnet/testnet/regtest) this canister is configured to talk to.
+    static NETWORK: RefCell<BitcoinNetwork> = RefCell::new(BitcoinNetwork::Testnet);
+
+    // Name of the threshold ECDSA key this canister signs with. "dfx_test_key" for local
+    // replica, "test_key_1" on the mainnet test key, "key_1" for the production key.
+    static ECDSA_KEY_NAME: RefCell<String> = RefCell::new("dfx_test_key".to_string());
+}
+
+/// Derivation path for the canister's single wallet key. A real multi-wallet canister would vary
+/// this per caller/account; this canister has exactly one P2PKH address.
+pub fn derivation_path() -> Vec<Vec<u8>> {
+    vec![]
+}
+
+/// Returns the Bitcoin network this canister is currently configured for.
+pub fn network() -> BitcoinNetwork {
+    NETWORK.with(|n| *n.borrow())
+}
+
+/// Updates the configured Bitcoin network.
+pub fn set_network(network: BitcoinNetwork) {
+    NETWORK.with(|n| *n.borrow_mut() = network);
+}
+
+/// Returns the name of the threshold ECDSA key this canister signs with.
+pub fn ecdsa_key_name() -> String {
+    ECDSA_KEY_NAME.with(|k| k.borrow().clone())
+}
+
+/// Updates the threshold ECDSA key name.
+pub fn set_ecdsa_key_name(key_name: String) {
+    ECDSA_KEY_NAME.with(|k| *k.borrow_mut() = key_name);
+}
+
+/// Converts the Bitcoin API's network type into rust-bitcoin's, for address parsing/formatting.
+pub fn to_bitcoin_network(network: BitcoinNetwork) -> bitcoin::Network {
+    match network {
+        BitcoinNetwork::Mainnet => bitcoin::Network::Bitcoin,
+        BitcoinNetwork::Testnet => bitcoin::Network::Testnet,
+        BitcoinNetwork::Regtest => bitcoin::Network::Regtest,
+    }
+}